@@ -0,0 +1,292 @@
+/*---------------------------------------------------------------------------------------------
+ *  Copyright (c) Microsoft Corporation. All rights reserved.
+ *  Licensed under the MIT License. See License.txt in the project root for license information.
+ *--------------------------------------------------------------------------------------------*/
+
+use std::path::Path;
+
+// Same shape as the RPC-facing CodeChunk, minus `path` (the caller knows it).
+#[derive(Debug, Clone)]
+pub struct Chunk {
+	pub content: String,
+	pub start_line: u32,
+	pub end_line: u32,
+	pub chunk_type: String,
+}
+
+// Rough ceiling on chunk size, approximating ~4 characters per token.
+const MAX_CHUNK_CHARS: usize = 3200;
+
+// Lines re-included at the start of each subsequent sliding window.
+const OVERLAP_LINES: usize = 5;
+
+// Characters re-included at the start of each subsequent character-level
+// window, mirroring OVERLAP_LINES for the single-line fallback below.
+const OVERLAP_CHARS: usize = 200;
+
+const BINARY_EXTENSIONS: &[&str] = &[
+	"png", "jpg", "jpeg", "gif", "ico", "bmp", "webp", "svg", "woff", "woff2", "ttf", "eot", "otf", "zip", "tar", "gz",
+	"7z", "rar", "exe", "dll", "so", "dylib", "bin", "wasm", "pdf", "mp3", "mp4", "mov", "avi", "lock",
+];
+
+// Exclude-list rather than an allow-list: chunk_file() falls back to a plain
+// sliding window for any language it doesn't have boundary rules for, so
+// most text files are indexable even if Language can't classify them.
+pub fn is_indexable(path: &Path) -> bool {
+	match path.extension().and_then(|e| e.to_str()) {
+		Some(ext) => !BINARY_EXTENSIONS.contains(&ext.to_lowercase().as_str()),
+		None => true,
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+	Rust,
+	Python,
+	JavaScript,
+	Go,
+	Unknown,
+}
+
+impl Language {
+	fn from_path(path: &Path) -> Self {
+		match path.extension().and_then(|e| e.to_str()) {
+			Some("rs") => Language::Rust,
+			Some("py") => Language::Python,
+			Some("js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx") => Language::JavaScript,
+			Some("go") => Language::Go,
+			_ => Language::Unknown,
+		}
+	}
+}
+
+// Prefers to break at function/class/method starts for languages we
+// recognize; falls back to a sliding window with overlap otherwise, and also
+// falls back within a single segment that's still too large after a
+// syntactic split (e.g. a very long function).
+pub fn chunk_file(path: &Path, content: &str) -> Vec<Chunk> {
+	let language = Language::from_path(path);
+	let lines: Vec<&str> = content.lines().collect();
+	if lines.is_empty() {
+		return Vec::new();
+	}
+
+	let boundaries = find_boundaries(&lines, language);
+	let mut segments: Vec<(usize, usize, &'static str)> = Vec::new();
+	if boundaries.is_empty() {
+		segments.push((0, lines.len(), "block"));
+	} else {
+		// Leading lines before the first boundary (imports, module docs, etc.)
+		// become their own chunk rather than being dropped.
+		if boundaries[0].0 > 0 {
+			segments.push((0, boundaries[0].0, "block"));
+		}
+		for (i, &(start, chunk_type)) in boundaries.iter().enumerate() {
+			let end = boundaries.get(i + 1).map(|b| b.0).unwrap_or(lines.len());
+			segments.push((start, end, chunk_type));
+		}
+	}
+
+	let mut chunks = Vec::new();
+	for (start, end, chunk_type) in segments {
+		split_segment(&lines, start, end, chunk_type, &mut chunks);
+	}
+	chunks
+}
+
+fn find_boundaries(lines: &[&str], language: Language) -> Vec<(usize, &'static str)> {
+	if language == Language::Unknown {
+		return Vec::new();
+	}
+	lines
+		.iter()
+		.enumerate()
+		.filter_map(|(i, line)| classify_boundary(line, language).map(|chunk_type| (i, chunk_type)))
+		.collect()
+}
+
+fn classify_boundary(line: &str, language: Language) -> Option<&'static str> {
+	let trimmed = line.trim_start();
+	match language {
+		Language::Rust => {
+			let rest = strip_prefixes(trimmed, &["pub(crate) ", "pub ", "async ", "unsafe ", "extern \"C\" "]);
+			if starts_with_word(rest, "fn") {
+				Some("function")
+			} else if starts_with_word(rest, "impl")
+				|| starts_with_word(rest, "struct")
+				|| starts_with_word(rest, "enum")
+				|| starts_with_word(rest, "trait")
+			{
+				Some("type")
+			} else {
+				None
+			}
+		}
+		Language::Python => {
+			let rest = strip_prefixes(trimmed, &["async "]);
+			if starts_with_word(rest, "def") {
+				Some("function")
+			} else if starts_with_word(rest, "class") {
+				Some("class")
+			} else {
+				None
+			}
+		}
+		Language::JavaScript => {
+			let rest = strip_prefixes(trimmed, &["export default ", "export ", "async "]);
+			if starts_with_word(rest, "function") {
+				Some("function")
+			} else if starts_with_word(rest, "class") {
+				Some("class")
+			} else {
+				None
+			}
+		}
+		Language::Go => {
+			if starts_with_word(trimmed, "func") {
+				Some("function")
+			} else if starts_with_word(trimmed, "type") {
+				Some("type")
+			} else {
+				None
+			}
+		}
+		Language::Unknown => None,
+	}
+}
+
+fn strip_prefixes<'a>(mut s: &'a str, prefixes: &[&str]) -> &'a str {
+	loop {
+		match prefixes.iter().find_map(|p| s.strip_prefix(p)) {
+			Some(rest) => s = rest,
+			None => return s,
+		}
+	}
+}
+
+fn starts_with_word(s: &str, word: &str) -> bool {
+	s.strip_prefix(word)
+		.is_some_and(|rest| rest.is_empty() || rest.starts_with(|c: char| c.is_whitespace() || c == '(' || c == '<'))
+}
+
+fn split_segment(lines: &[&str], start: usize, end: usize, chunk_type: &str, out: &mut Vec<Chunk>) {
+	let text = lines[start..end].join("\n");
+	if text.len() <= MAX_CHUNK_CHARS {
+		out.push(Chunk {
+			content: text,
+			start_line: (start + 1) as u32,
+			end_line: end as u32,
+			chunk_type: chunk_type.to_string(),
+		});
+		return;
+	}
+
+	// A single line (e.g. a minified bundle or generated one-liner) can't be
+	// split further at the line level, so fall back to character windows.
+	if end - start <= 1 {
+		split_line_by_chars(&text, start, out);
+		return;
+	}
+
+	let mut window_start = start;
+	loop {
+		let window_end = find_window_end(lines, window_start, end);
+		out.push(Chunk {
+			content: lines[window_start..window_end].join("\n"),
+			start_line: (window_start + 1) as u32,
+			end_line: window_end as u32,
+			chunk_type: "block".to_string(),
+		});
+
+		if window_end >= end {
+			break;
+		}
+		window_start = window_end.saturating_sub(OVERLAP_LINES).max(window_start + 1);
+	}
+}
+
+// `line` is the 0-based index of the over-long source line.
+fn split_line_by_chars(text: &str, line: usize, out: &mut Vec<Chunk>) {
+	let chars: Vec<char> = text.chars().collect();
+	let mut window_start = 0;
+	loop {
+		let window_end = (window_start + MAX_CHUNK_CHARS).min(chars.len());
+		out.push(Chunk {
+			content: chars[window_start..window_end].iter().collect(),
+			start_line: (line + 1) as u32,
+			end_line: (line + 1) as u32,
+			chunk_type: "block".to_string(),
+		});
+
+		if window_end >= chars.len() {
+			break;
+		}
+		window_start = window_end.saturating_sub(OVERLAP_CHARS).max(window_start + 1);
+	}
+}
+
+// Walks forward until adding another line would exceed MAX_CHUNK_CHARS,
+// always including at least one line.
+fn find_window_end(lines: &[&str], window_start: usize, end: usize) -> usize {
+	let mut char_count = 0;
+	let mut i = window_start;
+	while i < end {
+		char_count += lines[i].len() + 1;
+		if char_count > MAX_CHUNK_CHARS && i > window_start {
+			break;
+		}
+		i += 1;
+	}
+	i.max(window_start + 1).min(end)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::path::PathBuf;
+
+	#[test]
+	fn splits_rust_on_fn_boundaries() {
+		let path = PathBuf::from("example.rs");
+		let content = "use std::fmt;\n\npub fn one() {\n    1\n}\n\nasync fn two() {\n    2\n}\n";
+		let chunks = chunk_file(&path, content);
+
+		assert_eq!(chunks.len(), 3);
+		assert_eq!(chunks[0].chunk_type, "block");
+		assert_eq!(chunks[1].chunk_type, "function");
+		assert!(chunks[1].content.starts_with("pub fn one()"));
+		assert_eq!(chunks[2].chunk_type, "function");
+		assert!(chunks[2].content.starts_with("async fn two()"));
+	}
+
+	#[test]
+	fn falls_back_to_sliding_window_for_unknown_languages() {
+		let path = PathBuf::from("notes.txt");
+		// 200 lines of 41 characters each, well past MAX_CHUNK_CHARS in total.
+		let lines: Vec<String> = (0..200).map(|i| format!("line {i:03} {}", "-".repeat(32))).collect();
+		let content = lines.join("\n");
+		let chunks = chunk_file(&path, &content);
+
+		assert!(chunks.len() > 1);
+		assert!(chunks.iter().all(|c| c.chunk_type == "block"));
+		assert_eq!(chunks[0].start_line, 1);
+		assert_eq!(chunks.last().unwrap().end_line, 200);
+	}
+
+	#[test]
+	fn splits_a_single_long_line_by_characters() {
+		let path = PathBuf::from("bundle.min.js");
+		let content = "x".repeat(MAX_CHUNK_CHARS * 3);
+		let chunks = chunk_file(&path, &content);
+
+		assert!(chunks.len() > 1);
+		assert!(chunks.iter().all(|c| c.content.len() <= MAX_CHUNK_CHARS));
+		assert!(chunks.iter().all(|c| c.start_line == 1 && c.end_line == 1));
+	}
+
+	#[test]
+	fn is_indexable_excludes_binary_extensions() {
+		assert!(is_indexable(&PathBuf::from("main.rs")));
+		assert!(!is_indexable(&PathBuf::from("logo.png")));
+	}
+}
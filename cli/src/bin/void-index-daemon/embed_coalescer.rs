@@ -0,0 +1,218 @@
+/*---------------------------------------------------------------------------------------------
+ *  Copyright (c) Microsoft Corporation. All rights reserved.
+ *  Licensed under the MIT License. See License.txt in the project root for license information.
+ *--------------------------------------------------------------------------------------------*/
+
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{Duration, Instant};
+
+use crate::embedding_provider::EmbeddingProvider;
+
+// Flush once this many requests have accumulated, even if MAX_BATCH_DELAY
+// hasn't elapsed yet.
+const MAX_BATCH_SIZE: usize = 32;
+
+// Flush whatever has accumulated once this long has passed since the first
+// request in the batch arrived.
+const MAX_BATCH_DELAY: Duration = Duration::from_millis(50);
+
+struct PendingRequest {
+	text: String,
+	reply: oneshot::Sender<Result<Vec<f32>>>,
+}
+
+// Coalesces embed() calls from concurrent callers into embed_batch() calls
+// against the underlying provider: accumulate until either MAX_BATCH_SIZE
+// items arrive or MAX_BATCH_DELAY elapses, then flush and fan the results
+// back out to each awaiter.
+#[derive(Clone)]
+pub struct EmbedCoalescer {
+	tx: mpsc::UnboundedSender<PendingRequest>,
+}
+
+impl EmbedCoalescer {
+	pub fn new(provider: Arc<dyn EmbeddingProvider>) -> Self {
+		let (tx, rx) = mpsc::unbounded_channel();
+		tokio::spawn(Self::run(provider, rx));
+		Self { tx }
+	}
+
+	pub async fn embed(&self, text: String) -> Result<Vec<f32>> {
+		let (reply_tx, reply_rx) = oneshot::channel();
+		self.tx
+			.send(PendingRequest { text, reply: reply_tx })
+			.map_err(|_| anyhow::anyhow!("Embedding coalescer has shut down"))?;
+
+		reply_rx
+			.await
+			.map_err(|_| anyhow::anyhow!("Embedding coalescer dropped the request"))?
+	}
+
+	async fn run(provider: Arc<dyn EmbeddingProvider>, mut rx: mpsc::UnboundedReceiver<PendingRequest>) {
+		let mut batch: Vec<PendingRequest> = Vec::with_capacity(MAX_BATCH_SIZE);
+		// Anchored to the first arrival in a batch, not reset by every item.
+		let mut deadline: Option<Instant> = None;
+
+		loop {
+			let should_flush = if batch.is_empty() {
+				match rx.recv().await {
+					Some(request) => {
+						batch.push(request);
+						deadline = Some(Instant::now() + MAX_BATCH_DELAY);
+						false
+					}
+					None => break,
+				}
+			} else {
+				tokio::select! {
+					request = rx.recv() => match request {
+						Some(request) => {
+							batch.push(request);
+							batch.len() >= MAX_BATCH_SIZE
+						}
+						None => true,
+					},
+					_ = tokio::time::sleep_until(deadline.unwrap()) => true,
+				}
+			};
+
+			if should_flush || batch.len() >= MAX_BATCH_SIZE {
+				deadline = None;
+				Self::flush(&provider, std::mem::take(&mut batch)).await;
+			}
+		}
+
+		Self::flush(&provider, batch).await;
+	}
+
+	async fn flush(provider: &Arc<dyn EmbeddingProvider>, batch: Vec<PendingRequest>) {
+		if batch.is_empty() {
+			return;
+		}
+
+		let texts: Vec<String> = batch.iter().map(|r| r.text.clone()).collect();
+		match provider.embed_batch(&texts).await {
+			Ok(embeddings) if embeddings.len() == batch.len() => {
+				for (request, embedding) in batch.into_iter().zip(embeddings) {
+					let _ = request.reply.send(Ok(embedding));
+				}
+			}
+			Ok(_) => {
+				for request in batch {
+					let _ = request
+						.reply
+						.send(Err(anyhow::anyhow!("Embedding provider returned a mismatched batch size")));
+				}
+			}
+			Err(e) => {
+				let message = e.to_string();
+				for request in batch {
+					let _ = request.reply.send(Err(anyhow::anyhow!(message.clone())));
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Mutex;
+
+	#[derive(Default)]
+	struct RecordingProvider {
+		batch_sizes: Mutex<Vec<usize>>,
+	}
+
+	impl RecordingProvider {
+		fn batch_sizes(&self) -> Vec<usize> {
+			self.batch_sizes.lock().unwrap().clone()
+		}
+	}
+
+	#[async_trait::async_trait]
+	impl EmbeddingProvider for RecordingProvider {
+		async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+			Ok(self.embed_batch(&[text.to_string()]).await?.remove(0))
+		}
+
+		async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+			self.batch_sizes.lock().unwrap().push(texts.len());
+			Ok(texts.iter().map(|_| vec![0.0]).collect())
+		}
+
+		fn dimensions(&self) -> usize {
+			1
+		}
+
+		fn id(&self) -> String {
+			"recording".to_string()
+		}
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn flushes_when_batch_size_reached() {
+		let provider = Arc::new(RecordingProvider::default());
+		let coalescer = EmbedCoalescer::new(provider.clone());
+
+		let handles: Vec<_> = (0..MAX_BATCH_SIZE)
+			.map(|i| {
+				let coalescer = coalescer.clone();
+				tokio::spawn(async move { coalescer.embed(format!("text-{i}")).await })
+			})
+			.collect();
+
+		for handle in handles {
+			assert!(handle.await.unwrap().is_ok());
+		}
+
+		// All MAX_BATCH_SIZE requests should have landed in a single batch,
+		// flushed as soon as the size threshold was hit -- no need to wait
+		// for MAX_BATCH_DELAY to elapse.
+		assert_eq!(provider.batch_sizes(), vec![MAX_BATCH_SIZE]);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn flushes_after_max_batch_delay_with_no_further_arrivals() {
+		let provider = Arc::new(RecordingProvider::default());
+		let coalescer = EmbedCoalescer::new(provider.clone());
+
+		let result = coalescer.embed("hello".to_string()).await;
+
+		assert!(result.is_ok());
+		assert_eq!(provider.batch_sizes(), vec![1]);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn anchors_flush_deadline_to_first_arrival() {
+		let provider = Arc::new(RecordingProvider::default());
+		let coalescer = EmbedCoalescer::new(provider.clone());
+		let start = Instant::now();
+
+		let first = {
+			let coalescer = coalescer.clone();
+			tokio::spawn(async move { coalescer.embed("first".to_string()).await })
+		};
+
+		// Give the run loop a chance to receive `first` and anchor its flush
+		// deadline before `second` arrives.
+		tokio::time::sleep(Duration::from_millis(10)).await;
+
+		let second = {
+			let coalescer = coalescer.clone();
+			tokio::spawn(async move { coalescer.embed("second".to_string()).await })
+		};
+
+		assert!(first.await.unwrap().is_ok());
+		assert!(second.await.unwrap().is_ok());
+
+		// Anchored to `first`'s arrival: the flush lands MAX_BATCH_DELAY after
+		// `first`, not MAX_BATCH_DELAY after `second` -- which would put
+		// elapsed time at ~60ms instead of 50ms. That reset-per-item behavior
+		// is the bug this test guards against.
+		assert_eq!(Instant::now() - start, MAX_BATCH_DELAY);
+		assert_eq!(provider.batch_sizes(), vec![2]);
+	}
+}
@@ -0,0 +1,216 @@
+/*---------------------------------------------------------------------------------------------
+ *  Copyright (c) Microsoft Corporation. All rights reserved.
+ *  Licensed under the MIT License. See License.txt in the project root for license information.
+ *--------------------------------------------------------------------------------------------*/
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::ollama_client::OllamaClient;
+
+// OllamaClient, OpenAIProvider and StaticProvider all implement this so the
+// rest of the daemon can be built against a single Arc<dyn EmbeddingProvider>.
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+	async fn embed(&self, text: &str) -> Result<Vec<f32>>;
+
+	// Default calls embed() once per text; implementors can override with a
+	// real batch endpoint.
+	async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+		let mut out = Vec::with_capacity(texts.len());
+		for text in texts {
+			out.push(self.embed(text).await?);
+		}
+		Ok(out)
+	}
+
+	fn dimensions(&self) -> usize;
+
+	// e.g. "ollama:nomic-embed-text", for logging/diagnostics.
+	fn id(&self) -> String;
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaClient {
+	async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+		OllamaClient::embed(self, text).await
+	}
+
+	async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+		OllamaClient::embed_batch(self, texts).await
+	}
+
+	fn dimensions(&self) -> usize {
+		self.dimensions()
+	}
+
+	fn id(&self) -> String {
+		format!("ollama:{}", self.model())
+	}
+}
+
+// Talks to an OpenAI-compatible /v1/embeddings endpoint.
+#[derive(Debug, Clone)]
+pub struct OpenAIProvider {
+	client: Client,
+	base_url: String,
+	model: String,
+	api_key: String,
+	dimensions: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIEmbedRequest<'a> {
+	input: &'a [String],
+	model: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbedResponse {
+	data: Vec<OpenAIEmbedData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbedData {
+	embedding: Vec<f32>,
+	index: usize,
+}
+
+impl OpenAIProvider {
+	pub fn new(base_url: &str, model: &str, api_key: &str, dimensions: usize) -> Result<Self> {
+		let client = Client::builder()
+			.timeout(std::time::Duration::from_secs(60))
+			.build()
+			.context("Failed to create HTTP client")?;
+
+		Ok(Self {
+			client,
+			base_url: base_url.trim_end_matches('/').to_string(),
+			model: model.to_string(),
+			api_key: api_key.to_string(),
+			dimensions,
+		})
+	}
+
+	async fn embed_request(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+		let url = format!("{}/v1/embeddings", self.base_url);
+		let request = OpenAIEmbedRequest {
+			input: texts,
+			model: &self.model,
+		};
+
+		let response = self
+			.client
+			.post(&url)
+			.bearer_auth(&self.api_key)
+			.json(&request)
+			.send()
+			.await
+			.context("Failed to send embedding request to OpenAI")?;
+
+		if !response.status().is_success() {
+			let status = response.status();
+			let body = response.text().await.unwrap_or_default();
+			return Err(anyhow::anyhow!(
+				"OpenAI API returned error {}: {}",
+				status,
+				body
+			));
+		}
+
+		let mut embed_response: OpenAIEmbedResponse = response
+			.json()
+			.await
+			.context("Failed to parse embedding response")?;
+
+		embed_response.data.sort_by_key(|d| d.index);
+		Ok(embed_response.data.into_iter().map(|d| d.embedding).collect())
+	}
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAIProvider {
+	async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+		let mut embeddings = self.embed_request(&[text.to_string()]).await?;
+		embeddings
+			.pop()
+			.ok_or_else(|| anyhow::anyhow!("OpenAI returned no embeddings"))
+	}
+
+	async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+		self.embed_request(texts).await
+	}
+
+	fn dimensions(&self) -> usize {
+		self.dimensions
+	}
+
+	fn id(&self) -> String {
+		format!("openai:{}", self.model)
+	}
+}
+
+// Deterministic, offline provider for tests and for running the daemon
+// without an embedding backend installed. Hashes the input text instead of
+// calling out to a model.
+#[derive(Debug, Clone)]
+pub struct StaticProvider {
+	dimensions: usize,
+}
+
+impl StaticProvider {
+	pub fn new(dimensions: usize) -> Self {
+		Self { dimensions }
+	}
+
+	fn hash_embed(&self, text: &str) -> Vec<f32> {
+		let mut vector = vec![0.0f32; self.dimensions];
+		for (i, byte) in text.bytes().enumerate() {
+			let slot = i % self.dimensions;
+			vector[slot] += byte as f32;
+		}
+		vector
+	}
+}
+
+#[async_trait]
+impl EmbeddingProvider for StaticProvider {
+	async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+		Ok(self.hash_embed(text))
+	}
+
+	fn dimensions(&self) -> usize {
+		self.dimensions
+	}
+
+	fn id(&self) -> String {
+		"static".to_string()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn static_provider_is_deterministic() {
+		let provider = StaticProvider::new(8);
+		let a = provider.embed("hello world").await.unwrap();
+		let b = provider.embed("hello world").await.unwrap();
+		assert_eq!(a, b);
+		assert_eq!(a.len(), 8);
+	}
+
+	#[tokio::test]
+	async fn static_provider_batches_match_single() {
+		let provider = StaticProvider::new(8);
+		let batch = provider
+			.embed_batch(&["foo".to_string(), "bar".to_string()])
+			.await
+			.unwrap();
+		assert_eq!(batch[0], provider.embed("foo").await.unwrap());
+		assert_eq!(batch[1], provider.embed("bar").await.unwrap());
+	}
+}
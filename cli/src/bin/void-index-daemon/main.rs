@@ -3,6 +3,9 @@
  *  Licensed under the MIT License. See License.txt in the project root for license information.
  *--------------------------------------------------------------------------------------------*/
 
+mod chunker;
+mod embed_coalescer;
+mod embedding_provider;
 mod ollama_client;
 mod vector_store;
 
@@ -12,20 +15,51 @@ use cli::log;
 use cli::rpc;
 use cli::util::errors::AnyError;
 use cli::util::sync::{Barrier, Receivable};
+use embed_coalescer::EmbedCoalescer;
+use embedding_provider::{EmbeddingProvider, OpenAIProvider, StaticProvider};
+use ollama_client::OllamaClient;
 use opentelemetry::sdk::trace::TracerProvider as SdkTracerProvider;
 use opentelemetry::trace::TracerProvider;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::io;
+use vector_store::InsertRow;
+
+// Defaults to Ollama (using ollama_url/ollama_model if given) so existing
+// clients that don't send `provider` keep working unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum ProviderConfig {
+	Ollama {
+		url: Option<String>,
+		model: Option<String>,
+	},
+	OpenAi {
+		base_url: Option<String>,
+		model: String,
+		api_key: String,
+		dimensions: Option<usize>,
+	},
+	Static {
+		dimensions: Option<usize>,
+	},
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct InitializeParams {
 	workspace_path: String,
+	provider: Option<ProviderConfig>,
 	ollama_url: Option<String>,
 	ollama_model: Option<String>,
 	db_path: Option<String>,
+	// "cosine" (default), "l2", or "dot".
+	distance_type: Option<String>,
+	// Row count past which an IVF_PQ index is built automatically. Defaults
+	// to VectorStore's own threshold if omitted.
+	index_row_threshold: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +69,31 @@ struct IndexChunksParams {
 	chunks: Vec<CodeChunk>,
 }
 
+// This many rows indexed or more triggers an automatic maybe_create_index
+// check, so the index stays current without an explicit optimizeIndex call.
+const AUTO_OPTIMIZE_CHUNK_THRESHOLD: usize = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexFilesParams {
+	// Files to (re)index, relative to workspace_path or absolute. When
+	// omitted, workspace_path is walked for indexable source files.
+	paths: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexFilesResult {
+	files_indexed: usize,
+	chunks_indexed: usize,
+	chunks_skipped: usize,
+	chunks_deleted: usize,
+}
+
+// Directories never worth descending into: version control, dependencies,
+// build output, and the daemon's own index.
+const IGNORED_DIRS: &[&str] = &[".git", ".void", "node_modules", "target", "dist", "out", "build", "vendor"];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct InitializeResult {
@@ -46,6 +105,24 @@ struct InitializeResult {
 struct SearchParams {
 	query: String,
 	limit: Option<u32>,
+	// "vector" (default), "keyword", or "hybrid".
+	mode: Option<String>,
+	// Overrides the workspace's default distance metric for this query only.
+	distance_type: Option<String>,
+	// Number of IVF partitions to probe; higher trades speed for recall.
+	nprobes: Option<u32>,
+	// Over-fetch factor used to re-rank ANN candidates against full vectors.
+	refine_factor: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OptimizeIndexParams {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OptimizeIndexResult {
+	indexed: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,13 +142,195 @@ struct SearchResult {
 	scores: Vec<f32>,
 }
 
+#[derive(Clone)]
 struct IndexingContext {
 	workspace_path: PathBuf,
 	vector_store: Arc<vector_store::VectorStore>,
-	ollama_client: Arc<ollama_client::OllamaClient>,
+	embedding_provider: Arc<dyn EmbeddingProvider>,
+	embed_coalescer: EmbedCoalescer,
 	log: log::Logger,
 }
 
+// Falls back to the legacy ollama_url/ollama_model fields when no provider
+// is given.
+fn build_embedding_provider(params: &InitializeParams) -> Result<Arc<dyn EmbeddingProvider>> {
+	let provider = params.provider.clone().unwrap_or(ProviderConfig::Ollama {
+		url: params.ollama_url.clone(),
+		model: params.ollama_model.clone(),
+	});
+
+	Ok(match provider {
+		ProviderConfig::Ollama { url, model } => {
+			let url = url.unwrap_or_else(|| "http://localhost:11434".to_string());
+			let model = model.unwrap_or_else(|| "nomic-embed-text".to_string());
+			Arc::new(OllamaClient::new(&url, &model).context("Failed to create Ollama client")?)
+				as Arc<dyn EmbeddingProvider>
+		}
+		ProviderConfig::OpenAi {
+			base_url,
+			model,
+			api_key,
+			dimensions,
+		} => {
+			let base_url = base_url.unwrap_or_else(|| "https://api.openai.com".to_string());
+			let dimensions = dimensions.unwrap_or(1536);
+			Arc::new(
+				OpenAIProvider::new(&base_url, &model, &api_key, dimensions)
+					.context("Failed to create OpenAI provider")?,
+			) as Arc<dyn EmbeddingProvider>
+		}
+		ProviderConfig::Static { dimensions } => {
+			Arc::new(StaticProvider::new(dimensions.unwrap_or(768))) as Arc<dyn EmbeddingProvider>
+		}
+	})
+}
+
+// Resolves a path given to indexFiles against the workspace root, leaving
+// already-absolute paths untouched.
+fn resolve_workspace_path(workspace_path: &Path, path: &str) -> PathBuf {
+	let candidate = PathBuf::from(path);
+	if candidate.is_absolute() {
+		candidate
+	} else {
+		workspace_path.join(candidate)
+	}
+}
+
+// Recursively collects indexable source files under root, skipping hidden
+// and IGNORED_DIRS directories.
+fn walk_workspace(root: &Path) -> Result<Vec<PathBuf>> {
+	let mut files = Vec::new();
+	walk_dir(root, &mut files)?;
+	Ok(files)
+}
+
+fn walk_dir(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+	for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read directory {}", dir.display()))? {
+		let entry = entry?;
+		let path = entry.path();
+		let file_name = entry.file_name();
+		let file_name = file_name.to_string_lossy();
+
+		// `DirEntry::file_type` doesn't follow symlinks (unlike `Path::is_dir`),
+		// so a symlink -- including one that forms a directory cycle -- is
+		// never recursed into.
+		let file_type = entry.file_type().with_context(|| format!("Failed to stat {}", path.display()))?;
+		if file_type.is_symlink() {
+			continue;
+		}
+
+		if file_type.is_dir() {
+			if file_name.starts_with('.') || IGNORED_DIRS.contains(&file_name.as_ref()) {
+				continue;
+			}
+			walk_dir(&path, files)?;
+		} else if chunker::is_indexable(&path) {
+			files.push(path);
+		}
+	}
+	Ok(())
+}
+
+// Diffs chunks against what's already indexed for path, embeds only the
+// new/changed ones, writes them, and checks whether the ANN index should be
+// rebuilt. Shared by the indexChunks and indexFiles RPCs.
+async fn index_chunks_for_path(ctx: &IndexingContext, path: &str, chunks: Vec<CodeChunk>) -> Result<(usize, usize, usize)> {
+	let existing_hashes = ctx
+		.vector_store
+		.content_hashes_for_path(path)
+		.await
+		.context("Failed to read existing chunk hashes")?;
+
+	let chunk_hashes: Vec<String> = chunks.iter().map(|c| vector_store::hash_content(&c.content)).collect();
+	let incoming_hashes: HashSet<String> = chunk_hashes.iter().cloned().collect();
+	let deleted = existing_hashes.difference(&incoming_hashes).count();
+	let skipped = incoming_hashes.intersection(&existing_hashes).count();
+
+	ctx.vector_store
+		.delete_stale_for_path(path, &incoming_hashes)
+		.await
+		.context("Failed to delete stale chunks")?;
+
+	// Generate embeddings only for chunks that are new or changed. Each call
+	// goes through the embed coalescer, which batches concurrent requests --
+	// from this path's chunks and from any other in-flight calls -- into a
+	// handful of embed_batch round-trips instead of one HTTP request per chunk.
+	let mut tasks = Vec::new();
+	for (chunk, hash) in chunks.into_iter().zip(&chunk_hashes) {
+		if existing_hashes.contains(hash) {
+			continue;
+		}
+
+		let coalescer = ctx.embed_coalescer.clone();
+		let path = path.to_string();
+		let logger = ctx.log.clone();
+
+		tasks.push(tokio::spawn(async move {
+			let embedding = match coalescer.embed(chunk.content.clone()).await {
+				Ok(e) => e,
+				Err(e) => {
+					use cli::log;
+					log::warning!(logger, "Failed to generate embedding for {}: {}", path, e);
+					return Err(e);
+				}
+			};
+
+			Ok(InsertRow {
+				path,
+				content: chunk.content,
+				start_line: chunk.start_line,
+				end_line: chunk.end_line,
+				chunk_type: chunk.chunk_type,
+				embedding,
+			})
+		}));
+	}
+
+	// Wait for all embeddings, skipping any chunk that failed
+	let mut rows = Vec::with_capacity(tasks.len());
+	for task in tasks {
+		match task.await? {
+			Ok(row) => rows.push(row),
+			Err(e) => {
+				use cli::log;
+				log::warning!(ctx.log, "Error embedding chunk: {}", e);
+			}
+		}
+	}
+
+	// Write the new/changed chunks in a single RecordBatch insert
+	let indexed = rows.len();
+	ctx.vector_store.insert_batch(&rows).await.context("Failed to insert chunks")?;
+
+	info!(
+		ctx.log,
+		"Indexed {} chunks from {} ({} skipped, {} deleted)", indexed, path, skipped, deleted
+	);
+
+	// Large runs are exactly when a linear scan starts to hurt, so check
+	// whether it's time to (re)build the ANN index.
+	if indexed >= AUTO_OPTIMIZE_CHUNK_THRESHOLD {
+		maybe_optimize_index(ctx, indexed).await;
+	}
+
+	Ok((indexed, skipped, deleted))
+}
+
+// Best-effort ANN/FTS index (re)build check. maybe_create_index itself looks
+// at the table's total row count, not just `indexed`, so this is safe to call
+// after each indexChunks as well as once at the end of a bulk indexFiles run.
+// A failure here shouldn't fail the RPC that triggered it.
+async fn maybe_optimize_index(ctx: &IndexingContext, indexed: usize) {
+	match ctx.vector_store.maybe_create_index().await {
+		Ok(true) => info!(ctx.log, "Built ANN index after indexing {} chunks", indexed),
+		Ok(false) => {}
+		Err(e) => {
+			use cli::log;
+			log::warning!(ctx.log, "Failed to build ANN index: {}", e);
+		}
+	}
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
 	let tracer = SdkTracerProvider::builder().build().tracer("void-index-daemon");
@@ -94,9 +353,7 @@ async fn main() -> Result<()> {
 		let context = context_clone.clone();
 		async move {
 			let workspace_path = PathBuf::from(&params.workspace_path);
-			let ollama_url = params.ollama_url.unwrap_or_else(|| "http://localhost:11434".to_string());
-			let ollama_model = params.ollama_model.unwrap_or_else(|| "nomic-embed-text".to_string());
-			let db_path = params.db_path.unwrap_or_else(|| {
+			let db_path = params.db_path.clone().unwrap_or_else(|| {
 				workspace_path
 					.join(".void")
 					.join("index.lance")
@@ -104,9 +361,13 @@ async fn main() -> Result<()> {
 					.to_string()
 			});
 
+			// Build the embedding backend before logging anything else, since
+			// its id() tells us which provider actually got selected.
+			let embedding_provider = build_embedding_provider(&params)?;
+
 			info!(logger, "Initializing index daemon");
 			info!(logger, "Workspace: {}", workspace_path.display());
-			info!(logger, "Ollama URL: {}", ollama_url);
+			info!(logger, "Embedding provider: {}", embedding_provider.id());
 			info!(logger, "DB Path: {}", db_path);
 
 			// Create .void directory if it doesn't exist
@@ -116,23 +377,26 @@ async fn main() -> Result<()> {
 			}
 
 			// Initialize vector store
-			let vector_store = Arc::new(
-				vector_store::VectorStore::new(&db_path)
+			let distance_type = match &params.distance_type {
+				Some(dt) => vector_store::DistanceType::parse(dt)?,
+				None => vector_store::DistanceType::default(),
+			};
+			let vector_store = Arc::new(match params.index_row_threshold {
+				Some(threshold) => vector_store::VectorStore::with_options(&db_path, distance_type, threshold)
 					.await
 					.context("Failed to create vector store")?,
-			);
-
-			// Initialize Ollama client
-			let ollama_client = Arc::new(
-				ollama_client::OllamaClient::new(&ollama_url, &ollama_model)
-					.context("Failed to create Ollama client")?,
-			);
+				None => vector_store::VectorStore::with_distance_type(&db_path, distance_type)
+					.await
+					.context("Failed to create vector store")?,
+			});
 
 			// Store context
+			let embed_coalescer = EmbedCoalescer::new(embedding_provider.clone());
 			*context.lock().await = Some(IndexingContext {
 				workspace_path,
 				vector_store: vector_store.clone(),
-				ollama_client,
+				embedding_provider,
+				embed_coalescer,
 				log: logger.clone(),
 			});
 
@@ -149,57 +413,111 @@ async fn main() -> Result<()> {
 			let ctx = context.lock().await;
 			let ctx = ctx.as_ref().ok_or_else(|| AnyError::from("Not initialized"))?;
 
-			// Delete old entries for this file first
-			ctx.vector_store
-				.delete_by_path(&params.path)
+			let (indexed, skipped, deleted) = index_chunks_for_path(ctx, &params.path, params.chunks).await?;
+
+			Ok(serde_json::json!({ "indexed": indexed, "skipped": skipped, "deleted": deleted }))
+		}
+	});
+
+	let context_clone = context.clone();
+	methods.register_async("indexFiles", move |params: IndexFilesParams, _| {
+		let context = context_clone.clone();
+		async move {
+			// Clone the Arc-backed context and drop the lock before the walk+
+			// embed+insert loop below, which can run for minutes on a real
+			// workspace -- holding the guard that long would block every other
+			// RPC (e.g. a `search` from the editor) behind this one call.
+			let ctx = context
+				.lock()
 				.await
-				.context("Failed to delete old entries")?;
-
-			// Process chunks in parallel (but limit concurrency)
-			let semaphore = Arc::new(tokio::sync::Semaphore::new(10));
-			let mut tasks = Vec::new();
-
-			for chunk in &params.chunks {
-				let sem = semaphore.clone();
-				let chunk_content = chunk.content.clone();
-				let ollama = ctx.ollama_client.clone();
-				let store = ctx.vector_store.clone();
-				let path = params.path.clone();
-				let chunk_type = chunk.chunk_type.clone();
-				let start_line = chunk.start_line;
-				let end_line = chunk.end_line;
-
-				tasks.push(tokio::spawn(async move {
-					let _permit = sem.acquire().await.unwrap();
-
-					// Generate embedding
-					let embedding = match ollama.embed(&chunk_content).await {
-						Ok(e) => e,
-						Err(e) => {
-							eprintln!("Failed to generate embedding: {}", e);
-							return Err(e);
-						}
-					};
-
-					// Store in vector database
-					store
-						.insert(&path, &chunk_content, start_line, end_line, &chunk_type, &embedding)
-						.await
-						.map_err(|e| anyhow::anyhow!("Failed to insert chunk: {}", e))
-				}));
+				.as_ref()
+				.ok_or_else(|| AnyError::from("Not initialized"))?
+				.clone();
+			let ctx = &ctx;
+
+			let files = match &params.paths {
+				Some(paths) => paths
+					.iter()
+					.map(|p| resolve_workspace_path(&ctx.workspace_path, p))
+					.collect(),
+				None => walk_workspace(&ctx.workspace_path).context("Failed to walk workspace")?,
+			};
+
+			let mut result = IndexFilesResult {
+				files_indexed: 0,
+				chunks_indexed: 0,
+				chunks_skipped: 0,
+				chunks_deleted: 0,
+			};
+
+			for file in files {
+				let content = match std::fs::read_to_string(&file) {
+					Ok(content) => content,
+					Err(e) => {
+						use cli::log;
+						log::warning!(ctx.log, "Skipping {}: {}", file.display(), e);
+						continue;
+					}
+				};
+
+				let path = file.to_string_lossy().to_string();
+				let chunks: Vec<CodeChunk> = chunker::chunk_file(&file, &content)
+					.into_iter()
+					.map(|c| CodeChunk {
+						path: path.clone(),
+						content: c.content,
+						start_line: c.start_line,
+						end_line: c.end_line,
+						chunk_type: c.chunk_type,
+					})
+					.collect();
+
+				if chunks.is_empty() {
+					continue;
+				}
+
+				let (indexed, skipped, deleted) = match index_chunks_for_path(ctx, &path, chunks).await {
+					Ok(counts) => counts,
+					Err(e) => {
+						use cli::log;
+						log::warning!(ctx.log, "Skipping {}: {}", path, e);
+						continue;
+					}
+				};
+				result.files_indexed += 1;
+				result.chunks_indexed += indexed;
+				result.chunks_skipped += skipped;
+				result.chunks_deleted += deleted;
 			}
 
-			// Wait for all chunks to be processed
-			for task in tasks {
-				if let Err(e) = task.await? {
-					use cli::log;
-					log::warning!(ctx.log, "Error processing chunk: {}", e);
-				}
+			// index_chunks_for_path only checks its own per-file chunk count
+			// against AUTO_OPTIMIZE_CHUNK_THRESHOLD, so a bulk run made of many
+			// small files needs its own check against the cumulative total.
+			if result.chunks_indexed >= AUTO_OPTIMIZE_CHUNK_THRESHOLD {
+				maybe_optimize_index(ctx, result.chunks_indexed).await;
 			}
 
-			info!(ctx.log, "Successfully indexed {} chunks from {}", params.chunks.len(), params.path);
+			Ok(result)
+		}
+	});
 
-			Ok(serde_json::json!({ "indexed": params.chunks.len() }))
+	let context_clone = context.clone();
+	methods.register_async("optimizeIndex", move |_params: OptimizeIndexParams, _| {
+		let context = context_clone.clone();
+		async move {
+			let ctx = context.lock().await;
+			let ctx = ctx.as_ref().ok_or_else(|| AnyError::from("Not initialized"))?;
+
+			ctx.vector_store
+				.create_index()
+				.await
+				.context("Failed to build ANN index")?;
+			ctx.vector_store
+				.create_fts_index()
+				.await
+				.context("Failed to build full-text index")?;
+
+			Ok(OptimizeIndexResult { indexed: true })
 		}
 	});
 
@@ -210,18 +528,37 @@ async fn main() -> Result<()> {
 			let ctx = context.lock().await;
 			let ctx = ctx.as_ref().ok_or_else(|| AnyError::from("Not initialized"))?;
 
-			// Generate embedding for query
-			let query_embedding = ctx
-				.ollama_client
-				.embed(&params.query)
-				.await
-				.context("Failed to generate query embedding")?;
+			let mode = match &params.mode {
+				Some(m) => vector_store::SearchMode::parse(m)?,
+				None => vector_store::SearchMode::default(),
+			};
+
+			// Keyword-only search doesn't need an embedding at all
+			let query_embedding = if mode == vector_store::SearchMode::Keyword {
+				Vec::new()
+			} else {
+				ctx.embedding_provider
+					.embed(&params.query)
+					.await
+					.context("Failed to generate query embedding")?
+			};
 
 			// Search in vector store
 			let limit = params.limit.unwrap_or(10);
+			let distance_type = params
+				.distance_type
+				.as_deref()
+				.map(vector_store::DistanceType::parse)
+				.transpose()?;
+			let options = vector_store::SearchOptions {
+				mode,
+				distance_type,
+				nprobes: params.nprobes,
+				refine_factor: params.refine_factor,
+			};
 			let results = ctx
 				.vector_store
-				.search(&query_embedding, limit as usize)
+				.search_with_options(&query_embedding, &params.query, limit as usize, options)
 				.await
 				.context("Failed to search vector store")?;
 
@@ -7,11 +7,15 @@ use anyhow::{Context, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
+// Dimensionality of nomic-embed-text, used when the caller doesn't specify one.
+const DEFAULT_DIMENSIONS: usize = 768;
+
 #[derive(Debug, Clone)]
 pub struct OllamaClient {
 	client: Client,
 	base_url: String,
 	model: String,
+	dimensions: usize,
 }
 
 #[derive(Debug, Serialize)]
@@ -25,8 +29,23 @@ struct EmbedResponse {
 	embedding: Vec<f32>,
 }
 
+#[derive(Debug, Serialize)]
+struct BatchEmbedRequest<'a> {
+	input: &'a [String],
+	model: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchEmbedResponse {
+	embeddings: Vec<Vec<f32>>,
+}
+
 impl OllamaClient {
 	pub fn new(base_url: &str, model: &str) -> Result<Self> {
+		Self::with_dimensions(base_url, model, DEFAULT_DIMENSIONS)
+	}
+
+	pub fn with_dimensions(base_url: &str, model: &str, dimensions: usize) -> Result<Self> {
 		let client = Client::builder()
 			.timeout(std::time::Duration::from_secs(60))
 			.build()
@@ -36,9 +55,18 @@ impl OllamaClient {
 			client,
 			base_url: base_url.trim_end_matches('/').to_string(),
 			model: model.to_string(),
+			dimensions,
 		})
 	}
 
+	pub fn model(&self) -> &str {
+		&self.model
+	}
+
+	pub fn dimensions(&self) -> usize {
+		self.dimensions
+	}
+
 	pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
 		let url = format!("{}/api/embeddings", self.base_url);
 		let request = EmbedRequest {
@@ -72,6 +100,40 @@ impl OllamaClient {
 		Ok(embed_response.embedding)
 	}
 
+	// Embeds all texts in a single request via Ollama's /api/embed.
+	pub async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+		let url = format!("{}/api/embed", self.base_url);
+		let request = BatchEmbedRequest {
+			input: texts,
+			model: &self.model,
+		};
+
+		let response = self
+			.client
+			.post(&url)
+			.json(&request)
+			.send()
+			.await
+			.context("Failed to send batch embedding request to Ollama")?;
+
+		if !response.status().is_success() {
+			let status = response.status();
+			let body = response.text().await.unwrap_or_default();
+			return Err(anyhow::anyhow!(
+				"Ollama API returned error {}: {}",
+				status,
+				body
+			));
+		}
+
+		let embed_response: BatchEmbedResponse = response
+			.json()
+			.await
+			.context("Failed to parse batch embedding response")?;
+
+		Ok(embed_response.embeddings)
+	}
+
 	pub async fn health_check(&self) -> Result<()> {
 		let url = format!("{}/api/tags", self.base_url);
 		self.client
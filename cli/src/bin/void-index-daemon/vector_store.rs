@@ -9,8 +9,105 @@ use arrow::datatypes::Float32Type;
 use arrow_schema::{DataType, Field, Schema};
 use futures::TryStreamExt;
 use lancedb::connection::Connection;
+use lancedb::index::scalar::FtsIndexBuilder;
+use lancedb::index::vector::IvfPqIndexBuilder;
+use lancedb::index::Index;
+use lancedb::query::QueryBase;
 use lancedb::Database;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use tokio::sync::Mutex;
+
+// Below this many rows an exhaustive scan is already fast enough that an ANN
+// index would just add build overhead for no query-time benefit.
+const DEFAULT_INDEX_ROW_THRESHOLD: usize = 10_000;
+
+// Constant k in Reciprocal Rank Fusion: score = sum(1 / (k + rank)). 60 is
+// the standard value from the original RRF paper.
+const RRF_K: f32 = 60.0;
+
+// Embeddings are stored unit-normalized, so cosine and normalized L2 both
+// reduce to simple functions of the dot product.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceType {
+	Cosine,
+	L2,
+	Dot,
+}
+
+impl Default for DistanceType {
+	fn default() -> Self {
+		DistanceType::Cosine
+	}
+}
+
+impl DistanceType {
+	pub fn parse(value: &str) -> Result<Self> {
+		match value.to_ascii_lowercase().as_str() {
+			"cosine" => Ok(DistanceType::Cosine),
+			"l2" => Ok(DistanceType::L2),
+			"dot" => Ok(DistanceType::Dot),
+			other => Err(anyhow::anyhow!("Unknown distance type: {}", other)),
+		}
+	}
+
+	fn to_lancedb(self) -> lancedb::DistanceType {
+		match self {
+			DistanceType::Cosine => lancedb::DistanceType::Cosine,
+			DistanceType::L2 => lancedb::DistanceType::L2,
+			DistanceType::Dot => lancedb::DistanceType::Dot,
+		}
+	}
+
+	// Converts a raw _distance value into a similarity where higher is closer.
+	fn to_similarity(self, distance: f32) -> f32 {
+		match self {
+			DistanceType::Cosine | DistanceType::L2 => 1.0 - distance,
+			// LanceDB's dot distance is the negative dot product, so negate
+			// it back rather than inverting "closer" and "farther".
+			DistanceType::Dot => -distance,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMode {
+	#[default]
+	Vector,
+	Keyword,
+	Hybrid,
+}
+
+impl SearchMode {
+	pub fn parse(value: &str) -> Result<Self> {
+		match value.to_ascii_lowercase().as_str() {
+			"vector" => Ok(SearchMode::Vector),
+			"keyword" => Ok(SearchMode::Keyword),
+			"hybrid" => Ok(SearchMode::Hybrid),
+			other => Err(anyhow::anyhow!("Unknown search mode: {}", other)),
+		}
+	}
+}
+
+// Per-query overrides for search_with_options. A `None` field falls back to
+// the store's default (or LanceDB's default, for nprobes/refine_factor).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchOptions {
+	pub mode: SearchMode,
+	pub distance_type: Option<DistanceType>,
+	pub nprobes: Option<u32>,
+	pub refine_factor: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InsertRow {
+	pub path: String,
+	pub content: String,
+	pub start_line: u32,
+	pub end_line: u32,
+	pub chunk_type: String,
+	pub embedding: Vec<f32>,
+}
 
 #[derive(Debug, Clone)]
 pub struct SearchResult {
@@ -25,10 +122,24 @@ pub struct SearchResult {
 pub struct VectorStore {
 	db: Arc<Connection>,
 	table_name: String,
+	distance_type: DistanceType,
+	index_row_threshold: usize,
+	// Tracks whether the FTS index has been built yet, so keyword/hybrid
+	// search can lazily build it on first use instead of waiting on the
+	// ANN row threshold, which doesn't apply to FTS.
+	fts_index_ready: Mutex<bool>,
 }
 
 impl VectorStore {
 	pub async fn new(db_path: &str) -> Result<Self> {
+		Self::with_distance_type(db_path, DistanceType::default()).await
+	}
+
+	pub async fn with_distance_type(db_path: &str, distance_type: DistanceType) -> Result<Self> {
+		Self::with_options(db_path, distance_type, DEFAULT_INDEX_ROW_THRESHOLD).await
+	}
+
+	pub async fn with_options(db_path: &str, distance_type: DistanceType, index_row_threshold: usize) -> Result<Self> {
 		// Open or create database
 		let db = Database::connect(db_path)
 			.await
@@ -50,6 +161,9 @@ impl VectorStore {
 		Ok(Self {
 			db: Arc::new(db),
 			table_name: table_name.to_string(),
+			distance_type,
+			index_row_threshold,
+			fts_index_ready: Mutex::new(false),
 		})
 	}
 
@@ -62,48 +176,64 @@ impl VectorStore {
 		chunk_type: &str,
 		embedding: &[f32],
 	) -> Result<()> {
+		self.insert_batch(&[InsertRow {
+			path: path.to_string(),
+			content: content.to_string(),
+			start_line,
+			end_line,
+			chunk_type: chunk_type.to_string(),
+			embedding: embedding.to_vec(),
+		}])
+		.await
+	}
+
+	// Writes every row in a single RecordBatch/table call instead of one
+	// round-trip per chunk.
+	pub async fn insert_batch(&self, rows: &[InsertRow]) -> Result<()> {
 		use uuid::Uuid;
 
-		let id = Uuid::new_v4().to_string();
+		let Some(dimensions) = rows.first().map(|r| r.embedding.len()) else {
+			return Ok(());
+		};
 
-		// Define schema (vector dimension from embedding length)
+		// Define schema (vector dimension from the first row's embedding)
 		let schema = Arc::new(Schema::new(vec![
 			Field::new("id", DataType::Utf8, false),
 			Field::new("path", DataType::Utf8, false),
 			Field::new("content", DataType::Utf8, false),
+			Field::new("content_hash", DataType::Utf8, false),
 			Field::new("start_line", DataType::UInt32, false),
 			Field::new("end_line", DataType::UInt32, false),
 			Field::new("chunk_type", DataType::Utf8, false),
 			Field::new(
 				"vector",
-				DataType::FixedSizeList(
-					Arc::new(Field::new("item", DataType::Float32, true)),
-					embedding.len(),
-				),
+				DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), dimensions as i32),
 				false,
 			),
 		]));
 
-		let ids = StringArray::from(vec![id.clone()]);
-		let paths = StringArray::from(vec![path]);
-		let contents = StringArray::from(vec![content]);
-		let start_lines = UInt32Array::from(vec![start_line]);
-		let end_lines = UInt32Array::from(vec![end_line]);
-		let chunk_types = StringArray::from(vec![chunk_type]);
+		let ids: Vec<String> = (0..rows.len()).map(|_| Uuid::new_v4().to_string()).collect();
+		let paths = StringArray::from(rows.iter().map(|r| r.path.as_str()).collect::<Vec<_>>());
+		let contents = StringArray::from(rows.iter().map(|r| r.content.as_str()).collect::<Vec<_>>());
+		let content_hashes = StringArray::from(rows.iter().map(|r| hash_content(&r.content)).collect::<Vec<_>>());
+		let start_lines = UInt32Array::from(rows.iter().map(|r| r.start_line).collect::<Vec<_>>());
+		let end_lines = UInt32Array::from(rows.iter().map(|r| r.end_line).collect::<Vec<_>>());
+		let chunk_types = StringArray::from(rows.iter().map(|r| r.chunk_type.as_str()).collect::<Vec<_>>());
 
-		// Create vector array using FixedSizeListArray::from_iter_primitive
-		let vector_values: Vec<Option<f32>> = embedding.iter().map(|&v| Some(v)).collect();
-		let vector_array = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
-			std::iter::once(Some(vector_values)),
-			embedding.len(),
-		);
+		let vector_values: Vec<Option<Vec<Option<f32>>>> = rows
+			.iter()
+			.map(|r| Some(normalize(&r.embedding).into_iter().map(Some).collect()))
+			.collect();
+		let vector_array =
+			FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(vector_values, dimensions as i32);
 
 		let batch = RecordBatch::try_new(
 			schema.clone(),
 			vec![
-				Arc::new(ids),
+				Arc::new(StringArray::from(ids)),
 				Arc::new(paths),
 				Arc::new(contents),
+				Arc::new(content_hashes),
 				Arc::new(start_lines),
 				Arc::new(end_lines),
 				Arc::new(chunk_types),
@@ -116,10 +246,7 @@ impl VectorStore {
 		match self.db.open_table(&self.table_name).await {
 			Ok(table) => {
 				// Table exists, add to it
-				let batches = RecordBatchIterator::new(
-					vec![batch],
-					schema.clone(),
-				);
+				let batches = RecordBatchIterator::new(vec![batch], schema.clone());
 				table
 					.add(batches)
 					.execute()
@@ -128,10 +255,7 @@ impl VectorStore {
 			}
 			Err(_) => {
 				// Create table with first batch
-				let batches = RecordBatchIterator::new(
-					vec![batch],
-					schema.clone(),
-				);
+				let batches = RecordBatchIterator::new(vec![batch], schema.clone());
 				self.db
 					.create_table(&self.table_name, batches, None)
 					.await
@@ -142,90 +266,390 @@ impl VectorStore {
 		Ok(())
 	}
 
+	// Builds the ANN and full-text indexes once the table has grown past
+	// index_row_threshold. optimizeIndex forces both unconditionally instead.
+	pub async fn maybe_create_index(&self) -> Result<bool> {
+		let table = self
+			.db
+			.open_table(&self.table_name)
+			.await
+			.context("Failed to open table")?;
+
+		let row_count = table.count_rows(None).await.context("Failed to count rows")?;
+		if row_count < self.index_row_threshold {
+			return Ok(false);
+		}
+
+		self.create_index().await?;
+		self.create_fts_index().await?;
+		Ok(true)
+	}
+
+	pub async fn create_index(&self) -> Result<()> {
+		let table = self
+			.db
+			.open_table(&self.table_name)
+			.await
+			.context("Failed to open table")?;
+
+		table
+			.create_index(&["vector"], Index::IvfPq(IvfPqIndexBuilder::default()))
+			.execute()
+			.await
+			.context("Failed to create ANN index")?;
+
+		Ok(())
+	}
+
+	// Full-text index on `content` so keyword/hybrid search can match exact
+	// identifiers and error strings that embedding search tends to miss.
+	pub async fn create_fts_index(&self) -> Result<()> {
+		self.build_fts_index().await?;
+		*self.fts_index_ready.lock().await = true;
+		Ok(())
+	}
+
+	async fn build_fts_index(&self) -> Result<()> {
+		let table = self
+			.db
+			.open_table(&self.table_name)
+			.await
+			.context("Failed to open table")?;
+
+		table
+			.create_index(&["content"], Index::FTS(FtsIndexBuilder::default()))
+			.execute()
+			.await
+			.context("Failed to create full-text index")?;
+
+		Ok(())
+	}
+
+	// Keyword/hybrid search needs an FTS index regardless of table size, unlike
+	// the ANN index (gated on index_row_threshold because small tables don't
+	// benefit from it). Build it on first use instead of waiting on that
+	// threshold, which would leave any workspace smaller than it unable to run
+	// its very first keyword/hybrid query. Holds the lock across the build so
+	// concurrent callers can't both see "not ready" and build it twice.
+	async fn ensure_fts_index(&self) -> Result<()> {
+		let mut ready = self.fts_index_ready.lock().await;
+		if *ready {
+			return Ok(());
+		}
+		self.build_fts_index().await?;
+		*ready = true;
+		Ok(())
+	}
+
 	pub async fn search(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<SearchResult>> {
-		use arrow_array::Array;
+		self.search_with_options(query_embedding, "", limit, SearchOptions::default()).await
+	}
+
+	// query_text is only used for Keyword/Hybrid mode; pass the text that
+	// produced query_embedding so Hybrid can run both searches in parallel.
+	pub async fn search_with_options(
+		&self,
+		query_embedding: &[f32],
+		query_text: &str,
+		limit: usize,
+		options: SearchOptions,
+	) -> Result<Vec<SearchResult>> {
+		let distance_type = options.distance_type.unwrap_or(self.distance_type);
+
+		match options.mode {
+			SearchMode::Vector => {
+				let rows = self
+					.vector_search_rows(query_embedding, limit, distance_type, options.nprobes, options.refine_factor)
+					.await?;
+				Ok(rows.into_iter().map(|(_, row)| row).collect())
+			}
+			SearchMode::Keyword => {
+				self.ensure_fts_index().await?;
+				let rows = self.keyword_search_rows(query_text, limit).await?;
+				Ok(rows.into_iter().map(|(_, row)| row).collect())
+			}
+			SearchMode::Hybrid => {
+				self.ensure_fts_index().await?;
+				let (vector_rows, keyword_rows) = tokio::try_join!(
+					self.vector_search_rows(query_embedding, limit, distance_type, options.nprobes, options.refine_factor),
+					self.keyword_search_rows(query_text, limit),
+				)?;
+				Ok(fuse_rrf(vector_rows, keyword_rows, limit))
+			}
+		}
+	}
 
+	async fn vector_search_rows(
+		&self,
+		query_embedding: &[f32],
+		limit: usize,
+		distance_type: DistanceType,
+		nprobes: Option<u32>,
+		refine_factor: Option<u32>,
+	) -> Result<Vec<(String, SearchResult)>> {
 		let table = self
 			.db
 			.open_table(&self.table_name)
 			.await
 			.context("Failed to open table")?;
 
-		// Perform vector search using nearest_to
-		let query_vec: Vec<f32> = query_embedding.to_vec();
-		let results = table
+		// Perform vector search using nearest_to, requesting the computed
+		// `_distance` column so we can turn it into a similarity score below.
+		let query_vec: Vec<f32> = normalize(query_embedding);
+		let mut query = table
 			.query()
 			.nearest_to(&query_vec)
 			.context("Failed to create query")?
+			.distance_type(distance_type.to_lancedb())
+			.limit(limit);
+
+		if let Some(nprobes) = nprobes {
+			query = query.nprobes(nprobes as usize);
+		}
+		if let Some(refine_factor) = refine_factor {
+			query = query.refine_factor(refine_factor);
+		}
+
+		let results = query.execute().await.context("Failed to execute search")?;
+		let batches: Vec<RecordBatch> = results
+			.try_collect()
+			.await
+			.context("Failed to collect search results")?;
+
+		Ok(rows_from_batches(&batches, Some(distance_type)))
+	}
+
+	async fn keyword_search_rows(&self, query_text: &str, limit: usize) -> Result<Vec<(String, SearchResult)>> {
+		let table = self
+			.db
+			.open_table(&self.table_name)
+			.await
+			.context("Failed to open table")?;
+
+		let results = table
+			.query()
+			.full_text_search(query_text)
 			.limit(limit)
 			.execute()
 			.await
-			.context("Failed to execute search")?;
+			.context("Failed to execute full-text search")?;
 
 		let batches: Vec<RecordBatch> = results
 			.try_collect()
 			.await
-			.context("Failed to collect search results")?;
+			.context("Failed to collect full-text search results")?;
+
+		Ok(rows_from_batches(&batches, None))
+	}
+
+	pub async fn content_hashes_for_path(&self, path: &str) -> Result<HashSet<String>> {
+		use arrow_array::Array;
+
+		let table = match self.db.open_table(&self.table_name).await {
+			Ok(table) => table,
+			Err(_) => return Ok(HashSet::new()),
+		};
+
+		let escaped_path = path.replace('\'', "''");
+		let results = table
+			.query()
+			.only_if(format!("path = '{}'", escaped_path))
+			.execute()
+			.await
+			.context("Failed to query existing chunks")?;
 
-		let mut search_results = Vec::new();
-
-		// Process results
-		for batch in batches {
-			let path_col = batch
-				.column_by_name("path")
-				.context("Path column not found")?;
-			let content_col = batch
-				.column_by_name("content")
-				.context("Content column not found")?;
-			let start_line_col = batch
-				.column_by_name("start_line")
-				.context("Start line column not found")?;
-			let end_line_col = batch
-				.column_by_name("end_line")
-				.context("End line column not found")?;
-			let chunk_type_col = batch
-				.column_by_name("chunk_type")
-				.context("Chunk type column not found")?;
-
-			let path_array = path_col.as_any().downcast_ref::<StringArray>().unwrap();
-			let content_array = content_col.as_any().downcast_ref::<StringArray>().unwrap();
-			let start_line_array = start_line_col.as_any().downcast_ref::<UInt32Array>().unwrap();
-			let end_line_array = end_line_col.as_any().downcast_ref::<UInt32Array>().unwrap();
-			let chunk_type_array = chunk_type_col
-				.as_any()
-				.downcast_ref::<StringArray>()
-				.unwrap();
+		let batches: Vec<RecordBatch> = results
+			.try_collect()
+			.await
+			.context("Failed to collect existing chunks")?;
 
+		let mut hashes = HashSet::new();
+		for batch in &batches {
+			let Some(hash_col) = batch.column_by_name("content_hash") else { continue };
+			let hash_array = hash_col.as_any().downcast_ref::<StringArray>().unwrap();
 			for i in 0..batch.num_rows() {
-				search_results.push(SearchResult {
+				hashes.insert(hash_array.value(i).to_string());
+			}
+		}
+
+		Ok(hashes)
+	}
+
+	// Deletes chunks for `path` whose content_hash isn't in keep_hashes, i.e.
+	// chunks removed or edited since the last index of this file.
+	pub async fn delete_stale_for_path(&self, path: &str, keep_hashes: &HashSet<String>) -> Result<()> {
+		let table = match self.db.open_table(&self.table_name).await {
+			Ok(table) => table,
+			Err(_) => return Ok(()),
+		};
+
+		let mut predicate = format!("path = '{}'", path.replace('\'', "''"));
+		if !keep_hashes.is_empty() {
+			let kept = keep_hashes
+				.iter()
+				.map(|h| format!("'{}'", h.replace('\'', "''")))
+				.collect::<Vec<_>>()
+				.join(", ");
+			predicate.push_str(&format!(" AND content_hash NOT IN ({})", kept));
+		}
+
+		table
+			.delete(predicate)
+			.await
+			.context("Failed to delete stale chunks")?;
+
+		Ok(())
+	}
+}
+
+// Hashes chunk content with blake3 so unchanged chunks can be recognized on
+// reindex without re-embedding them.
+pub fn hash_content(content: &str) -> String {
+	blake3::hash(content.as_bytes()).to_hex().to_string()
+}
+
+// Unit-normalizes so cosine similarity between stored vectors reduces to a
+// plain dot product at query time. Leaves zero vectors as-is.
+fn normalize(embedding: &[f32]) -> Vec<f32> {
+	let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+	if norm == 0.0 {
+		return embedding.to_vec();
+	}
+	embedding.iter().map(|v| v / norm).collect()
+}
+
+// Keyword search has no _distance column, so its rows just get a score of
+// 0.0 -- fuse_rrf only needs row order from those, not a raw score.
+fn rows_from_batches(batches: &[RecordBatch], distance_type: Option<DistanceType>) -> Vec<(String, SearchResult)> {
+	use arrow_array::Array;
+
+	let mut rows = Vec::new();
+
+	for batch in batches {
+		let Some(id_col) = batch.column_by_name("id") else { continue };
+		let Some(path_col) = batch.column_by_name("path") else { continue };
+		let Some(content_col) = batch.column_by_name("content") else { continue };
+		let Some(start_line_col) = batch.column_by_name("start_line") else { continue };
+		let Some(end_line_col) = batch.column_by_name("end_line") else { continue };
+		let Some(chunk_type_col) = batch.column_by_name("chunk_type") else { continue };
+		let distance_col = batch.column_by_name("_distance");
+
+		let id_array = id_col.as_any().downcast_ref::<StringArray>().unwrap();
+		let path_array = path_col.as_any().downcast_ref::<StringArray>().unwrap();
+		let content_array = content_col.as_any().downcast_ref::<StringArray>().unwrap();
+		let start_line_array = start_line_col.as_any().downcast_ref::<UInt32Array>().unwrap();
+		let end_line_array = end_line_col.as_any().downcast_ref::<UInt32Array>().unwrap();
+		let chunk_type_array = chunk_type_col.as_any().downcast_ref::<StringArray>().unwrap();
+		let distance_array = distance_col.and_then(|c| c.as_any().downcast_ref::<Float32Array>());
+
+		for i in 0..batch.num_rows() {
+			let score = match (distance_type, distance_array) {
+				(Some(distance_type), Some(d)) => distance_type.to_similarity(d.value(i)),
+				_ => 0.0,
+			};
+
+			rows.push((
+				id_array.value(i).to_string(),
+				SearchResult {
 					path: path_array.value(i).to_string(),
 					content: content_array.value(i).to_string(),
 					start_line: start_line_array.value(i),
 					end_line: end_line_array.value(i),
 					chunk_type: chunk_type_array.value(i).to_string(),
-					score: 0.0, // Distance/score would be available if distance column is selected
-				});
-			}
+					score,
+				},
+			));
 		}
+	}
+
+	rows
+}
 
-		Ok(search_results)
+// Each id gets sum(1 / (RRF_K + rank)) over the lists it appears in (rank
+// 1-based per list); a document in only one list still scores on its own rank.
+fn fuse_rrf(vector_rows: Vec<(String, SearchResult)>, keyword_rows: Vec<(String, SearchResult)>, limit: usize) -> Vec<SearchResult> {
+	let mut fused: HashMap<String, (f32, SearchResult)> = HashMap::new();
+
+	for (rank, (id, row)) in vector_rows.into_iter().enumerate() {
+		let entry = fused.entry(id).or_insert_with(|| (0.0, row));
+		entry.0 += 1.0 / (RRF_K + (rank + 1) as f32);
 	}
 
-	pub async fn delete_by_path(&self, path: &str) -> Result<()> {
-		let table = self
-			.db
-			.open_table(&self.table_name)
-			.await
-			.context("Failed to open table")?;
+	for (rank, (id, row)) in keyword_rows.into_iter().enumerate() {
+		let entry = fused.entry(id).or_insert_with(|| (0.0, row));
+		entry.0 += 1.0 / (RRF_K + (rank + 1) as f32);
+	}
 
-		// Delete rows where path matches
-		// Note: LanceDB API for deletion may vary - this is a placeholder
-		// In practice, you'd use a delete operation with a filter
-		table
-			.delete(format!("path = '{}'", path.replace('\'', "''")))
-			.await
-			.context("Failed to delete from table")?;
+	let mut results: Vec<SearchResult> = fused
+		.into_values()
+		.map(|(score, mut row)| {
+			row.score = score;
+			row
+		})
+		.collect();
 
-		Ok(())
+	results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+	results.truncate(limit);
+	results
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn normalize_produces_a_unit_vector() {
+		let normalized = normalize(&[3.0, 4.0]);
+		let norm = normalized.iter().map(|v| v * v).sum::<f32>().sqrt();
+
+		assert!((norm - 1.0).abs() < 1e-6);
+		assert!((normalized[0] - 0.6).abs() < 1e-6);
+		assert!((normalized[1] - 0.8).abs() < 1e-6);
+	}
+
+	#[test]
+	fn normalize_leaves_zero_vector_unchanged() {
+		assert_eq!(normalize(&[0.0, 0.0, 0.0]), vec![0.0, 0.0, 0.0]);
+	}
+
+	fn fake_row(id: &str) -> (String, SearchResult) {
+		(
+			id.to_string(),
+			SearchResult {
+				path: format!("{id}.rs"),
+				content: String::new(),
+				start_line: 1,
+				end_line: 1,
+				chunk_type: "function".to_string(),
+				score: 0.0,
+			},
+		)
+	}
+
+	#[test]
+	fn fuse_rrf_combines_ranks_from_both_lists() {
+		// "a" ranks #1 vector and #2 keyword; "b" ranks #2 vector only;
+		// "c" ranks #1 keyword only. "a" appearing in both lists should win.
+		let vector_rows = vec![fake_row("a"), fake_row("b")];
+		let keyword_rows = vec![fake_row("c"), fake_row("a")];
+
+		let results = fuse_rrf(vector_rows, keyword_rows, 10);
+
+		let expected_a = 1.0 / (RRF_K + 1.0) + 1.0 / (RRF_K + 2.0);
+		let expected_b = 1.0 / (RRF_K + 2.0);
+		let expected_c = 1.0 / (RRF_K + 1.0);
+
+		assert_eq!(results.len(), 3);
+		assert_eq!(results[0].path, "a.rs");
+		assert!((results[0].score - expected_a).abs() < 1e-6);
+		assert!((results[1].score - expected_c.max(expected_b)).abs() < 1e-6);
+		assert!((results[2].score - expected_c.min(expected_b)).abs() < 1e-6);
+	}
+
+	#[test]
+	fn fuse_rrf_truncates_to_limit() {
+		let vector_rows = vec![fake_row("a"), fake_row("b"), fake_row("c")];
+		let results = fuse_rrf(vector_rows, Vec::new(), 2);
+		assert_eq!(results.len(), 2);
 	}
 }